@@ -0,0 +1,65 @@
+//! A `tokio_util` codec for framing [`Packet`]s over a streaming transport, split into a
+//! decoder and encoder the same way the tokio-tungstenite websocket codec splits its parser
+//! and writer. Gated behind the `tokio-codec` feature.
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{Packet, PacketError};
+
+/// Decodes and encodes [`Packet`]s for a framed `tokio` connection.
+///
+/// `decode` drives `Packet::from_bytes_streaming_with_blksize`, returning `Ok(None)` until a
+/// full packet has arrived and consuming only the bytes that packet used once it has. `encode`
+/// serializes with `Packet::to_bytes`. The configured block size should match whatever
+/// `blksize` (RFC 2348) was negotiated for the transfer, so DATA framing doesn't reject jumbo
+/// blocks.
+pub struct TftpCodec {
+    max_block: usize,
+}
+
+impl TftpCodec {
+    /// Create a codec that accepts DATA payloads up to the rfc1350 default of 512 bytes.
+    pub fn new() -> Self {
+        Self::with_blksize(512)
+    }
+
+    /// Create a codec that accepts DATA payloads up to `max_block` bytes, matching a
+    /// negotiated `blksize` (RFC 2348).
+    pub fn with_blksize(max_block: usize) -> Self {
+        Self { max_block }
+    }
+}
+
+impl Default for TftpCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for TftpCodec {
+    type Item = Packet;
+    type Error = PacketError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Packet>, PacketError> {
+        let (rest, packet) = Packet::from_bytes_streaming_with_blksize(src, self.max_block)?;
+        let consumed = src.len() - rest.len();
+
+        match packet {
+            Some(packet) => {
+                src.advance(consumed);
+                Ok(Some(packet))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<Packet> for TftpCodec {
+    type Error = PacketError;
+
+    fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), PacketError> {
+        dst.extend_from_slice(&item.to_bytes());
+        Ok(())
+    }
+}
@@ -9,7 +9,7 @@ This implements only conversion into and from bytes for a tftp packet and includ
 use tftp_packet::Packet;
 use tftp_packet::Mode;
 
-let packet = Packet::RRQ{ filename: "test.txt".to_string(), mode: Mode::Octet };
+let packet = Packet::RRQ{ filename: "test.txt".to_string(), mode: Mode::Octet, options: vec![] };
 let bytes = packet.clone().to_bytes();
 assert_eq!(bytes, [0, 1, 116, 101, 115, 116, 46, 116, 120, 116, 0, 111, 99, 116, 101, 116, 0]);
 assert_eq!(Packet::from_bytes(&bytes).unwrap(), packet);
@@ -17,14 +17,50 @@ assert_eq!(Packet::from_bytes(&bytes).unwrap(), packet);
 */
 mod parsing;
 
+mod netascii;
+
+pub use netascii::NetasciiDecoder;
+
+mod packet_ref;
+
+pub use packet_ref::PacketRef;
+
+#[cfg(feature = "tokio-codec")]
+mod codec;
+
+#[cfg(feature = "tokio-codec")]
+pub use codec::TftpCodec;
+
 use std::convert::TryFrom;
 use std::{error::Error, fmt::Display};
 
 use parsing::parse_block_number;
 use parsing::parse_filename;
 use parsing::parse_mode;
+use parsing::parse_options;
 use parsing::take_u16;
 use parsing::{parse_error_code, parse_error_message};
+use parsing::{
+    parse_block_number_streaming, parse_error_code_streaming, parse_error_message_streaming,
+    parse_filename_streaming, parse_mode_streaming, parse_options_streaming, take_u16_streaming,
+    PartialError,
+};
+
+/// Well-known option name for the negotiated block size, as defined by
+/// [RFC 2348](https://www.rfc-editor.org/rfc/rfc2348). Valid values are `8..=65464`.
+pub const OPTION_BLKSIZE: &str = "blksize";
+
+/// Well-known option name for the negotiated per-packet timeout, as defined by
+/// [RFC 2349](https://www.rfc-editor.org/rfc/rfc2349).
+pub const OPTION_TIMEOUT: &str = "timeout";
+
+/// Well-known option name for the transfer size, as defined by
+/// [RFC 2349](https://www.rfc-editor.org/rfc/rfc2349).
+pub const OPTION_TSIZE: &str = "tsize";
+
+/// Well-known option name for the negotiated window size, as defined by
+/// [RFC 7440](https://www.rfc-editor.org/rfc/rfc7440).
+pub const OPTION_WINDOWSIZE: &str = "windowsize";
 
 /// The error type for the tftp packet
 #[derive(Debug, PartialEq)]
@@ -51,7 +87,17 @@ impl Display for PacketError {
 
 impl Error for PacketError {}
 
-/// All tftp opcodes defined in rfc1350
+/// Lets `PacketError` stand in for I/O errors too, so it can be used as the associated
+/// `Error` type of a `tokio_util::codec::Decoder`/`Encoder` (see `TftpCodec` behind the
+/// `tokio-codec` feature), which requires that bound on its error type.
+impl From<std::io::Error> for PacketError {
+    fn from(error: std::io::Error) -> Self {
+        PacketError::InvalidPacket(format!("I/O error: {}", error))
+    }
+}
+
+/// All tftp opcodes defined in rfc1350, plus the `OACK` option acknowledgment
+/// opcode added by [RFC 2347](https://www.rfc-editor.org/rfc/rfc2347).
 ///
 /// ```
 /// # use tftp_packet::Opcode;
@@ -65,6 +111,7 @@ pub enum Opcode {
     DATA,
     ACK,
     ERROR,
+    OACK,
 }
 
 impl TryFrom<u16> for Opcode {
@@ -77,6 +124,7 @@ impl TryFrom<u16> for Opcode {
             3 => Opcode::DATA,
             4 => Opcode::ACK,
             5 => Opcode::ERROR,
+            6 => Opcode::OACK,
             _ => Err("Invalid opcode: {}")?,
         })
     }
@@ -178,10 +226,16 @@ pub enum Packet {
     RRQ {
         filename: String,
         mode: Mode,
+        /// Options requested for negotiation, as defined by
+        /// [RFC 2347](https://www.rfc-editor.org/rfc/rfc2347). Empty for a plain rfc1350 request.
+        options: Vec<(String, String)>,
     },
     WRQ {
         filename: String,
         mode: Mode,
+        /// Options requested for negotiation, as defined by
+        /// [RFC 2347](https://www.rfc-editor.org/rfc/rfc2347). Empty for a plain rfc1350 request.
+        options: Vec<(String, String)>,
     },
     DATA {
         block_number: u16,
@@ -194,6 +248,12 @@ pub enum Packet {
         error_code: ErrorCode,
         error_msg: String,
     },
+    /// Option acknowledgment, as defined by [RFC 2347](https://www.rfc-editor.org/rfc/rfc2347).
+    /// Sent in reply to an `RRQ`/`WRQ` that carried options, acknowledging the subset the
+    /// sender is willing to honor.
+    OACK {
+        options: Vec<(String, String)>,
+    },
 }
 
 impl Packet {
@@ -207,9 +267,25 @@ impl Packet {
     /// assert_eq!(packet, Packet::RRQ {
     ///    filename: "CDE".to_string(),
     ///    mode: Mode::Octet,
+    ///    options: vec![],
     /// });
     /// ```
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, PacketError> {
+        Self::from_bytes_with_blksize(bytes, 512)
+    }
+
+    /// Parse a packet from a byte array, accepting DATA payloads up to `max_block` bytes
+    /// instead of the rfc1350 default of 512. Use this once a transfer has negotiated a larger
+    /// `blksize` (RFC 2348, 8..=65464) via `RRQ`/`WRQ`/`OACK` options.
+    ///
+    /// ```
+    /// # use tftp_packet::Packet;
+    /// let mut packet = vec![0u8, 3, 0, 1];
+    /// packet.extend([42; 1024].iter());
+    /// assert!(Packet::from_bytes_with_blksize(&packet, 1024).is_ok());
+    /// assert!(Packet::from_bytes(&packet).is_err());
+    /// ```
+    pub fn from_bytes_with_blksize(bytes: &[u8], max_block: usize) -> Result<Self, PacketError> {
         let (bytes, opcode_bytes) = take_u16(bytes).map_err(|_| {
             PacketError::InvalidOpcode("Error while parsing opcode. Opcode not a u15.".to_string())
         })?;
@@ -221,23 +297,38 @@ impl Packet {
         match opcode {
             Opcode::RRQ => {
                 let (filename, bytes) = parse_filename(bytes)?;
-                let (mode, _bytes) = parse_mode(bytes)?;
+                let (mode, bytes) = parse_mode(bytes)?;
+                let (options, _bytes) = parse_options(bytes)?;
 
-                Ok(Packet::RRQ { filename, mode })
+                Ok(Packet::RRQ {
+                    filename,
+                    mode,
+                    options,
+                })
             }
             Opcode::WRQ => {
                 let (filename, bytes) = parse_filename(bytes)?;
-                let (mode, _bytes) = parse_mode(bytes)?;
+                let (mode, bytes) = parse_mode(bytes)?;
+                let (options, _bytes) = parse_options(bytes)?;
 
-                Ok(Packet::WRQ { filename, mode })
+                Ok(Packet::WRQ {
+                    filename,
+                    mode,
+                    options,
+                })
+            }
+            Opcode::OACK => {
+                let (options, _bytes) = parse_options(bytes)?;
+
+                Ok(Packet::OACK { options })
             }
             Opcode::DATA => {
                 let (block_number, bytes) = parse_block_number(bytes)?;
 
                 let data = bytes.to_vec();
 
-                if data.len() > 512 {
-                    Err(PacketError::InvalidPacketLength(512))?
+                if data.len() > max_block {
+                    Err(PacketError::InvalidPacketLength(max_block as u16))?
                 }
 
                 Ok(Packet::DATA { block_number, data })
@@ -263,6 +354,118 @@ impl Packet {
         }
     }
 
+    /// Parse a packet from a buffer that may not yet contain a full packet, such as one read
+    /// off a streaming transport.
+    ///
+    /// Returns `Ok((rest, None))` if a valid opcode was recognized but the buffer doesn't yet
+    /// hold the whole packet -- the caller should read more bytes and retry with `bytes`
+    /// unchanged (`rest` is the original input). Returns `Ok((rest, Some(packet)))` once a full
+    /// packet was decoded, with `rest` holding any trailing bytes. Returns `Err(..)` only for a
+    /// genuinely malformed buffer (bad opcode, invalid mode, non-UTF8 text) -- never for a
+    /// buffer that's merely short.
+    ///
+    /// ```
+    /// # use tftp_packet::Packet;
+    /// // a WRQ whose mode string hasn't arrived yet
+    /// let partial = &[0u8, 2, 67, 68, 69, 0, 0x6f, 0x63];
+    /// assert_eq!(Packet::from_bytes_streaming(partial).unwrap(), (&partial[..], None));
+    /// ```
+    pub fn from_bytes_streaming(bytes: &[u8]) -> Result<(&[u8], Option<Self>), PacketError> {
+        Self::from_bytes_streaming_with_blksize(bytes, 512)
+    }
+
+    /// Like `from_bytes_streaming`, but accepts DATA payloads up to `max_block` bytes instead
+    /// of the rfc1350 default of 512, for use once a `blksize` (RFC 2348) has been negotiated.
+    pub fn from_bytes_streaming_with_blksize(
+        bytes: &[u8],
+        max_block: usize,
+    ) -> Result<(&[u8], Option<Self>), PacketError> {
+        let original = bytes;
+
+        macro_rules! partial {
+            ($expr:expr) => {
+                match $expr {
+                    Ok(v) => v,
+                    Err(PartialError::Incomplete) => return Ok((original, None)),
+                    Err(PartialError::Malformed(e)) => return Err(e),
+                }
+            };
+        }
+
+        let (opcode_bytes, bytes) = partial!(take_u16_streaming(bytes));
+
+        let opcode = Opcode::try_from(opcode_bytes).map_err(|e| {
+            PacketError::InvalidOpcode(format!("Error while parsing opcode: {}", e))
+        })?;
+
+        match opcode {
+            Opcode::RRQ => {
+                let (filename, bytes) = partial!(parse_filename_streaming(bytes));
+                let (mode, bytes) = partial!(parse_mode_streaming(bytes));
+                let (options, bytes) = partial!(parse_options_streaming(bytes));
+
+                Ok((
+                    bytes,
+                    Some(Packet::RRQ {
+                        filename,
+                        mode,
+                        options,
+                    }),
+                ))
+            }
+            Opcode::WRQ => {
+                let (filename, bytes) = partial!(parse_filename_streaming(bytes));
+                let (mode, bytes) = partial!(parse_mode_streaming(bytes));
+                let (options, bytes) = partial!(parse_options_streaming(bytes));
+
+                Ok((
+                    bytes,
+                    Some(Packet::WRQ {
+                        filename,
+                        mode,
+                        options,
+                    }),
+                ))
+            }
+            Opcode::OACK => {
+                let (options, bytes) = partial!(parse_options_streaming(bytes));
+
+                Ok((bytes, Some(Packet::OACK { options })))
+            }
+            Opcode::DATA => {
+                let (block_number, bytes) = partial!(parse_block_number_streaming(bytes));
+
+                // There's no length field for DATA; the remainder of the buffer is the
+                // payload, same as `from_bytes`. A streaming transport is expected to frame
+                // datagrams before handing bytes here (see the tokio codec).
+                let data = bytes.to_vec();
+
+                if data.len() > max_block {
+                    return Err(PacketError::InvalidPacketLength(max_block as u16));
+                }
+
+                Ok((&[], Some(Packet::DATA { block_number, data })))
+            }
+            Opcode::ACK => {
+                let (block_number, bytes) = partial!(parse_block_number_streaming(bytes));
+
+                Ok((bytes, Some(Packet::ACK { block_number })))
+            }
+            Opcode::ERROR => {
+                let (error_code, bytes) = partial!(parse_error_code_streaming(bytes));
+                let (error_msg, bytes) = partial!(parse_error_message_streaming(bytes));
+
+                Ok((
+                    bytes,
+                    Some(Packet::ERROR {
+                        error_code,
+                        error_msg,
+                    }),
+                ))
+            }
+        }
+    }
+
     /// Serialize a packet into a byte array
     ///
     /// ```
@@ -271,26 +474,51 @@ impl Packet {
     /// let packet = Packet::RRQ {
     ///   filename: "CDE".to_string(),
     ///   mode: Mode::Octet,
+    ///   options: vec![],
     /// };
     /// let packet = packet.to_bytes();
     /// assert_eq!(packet, &[0u8, 1, 67, 68, 69, 0, 0x6f, 0x63, 0x74, 0x65, 0x74, 0]);
     /// ```
     pub fn to_bytes(self) -> Vec<u8> {
+        fn extend_with_options(bytes: &mut Vec<u8>, options: Vec<(String, String)>) {
+            for (name, value) in options {
+                bytes.extend(name.as_bytes());
+                bytes.push(0);
+                bytes.extend(value.as_bytes());
+                bytes.push(0);
+            }
+        }
+
         match self {
-            Packet::RRQ { filename, mode } => {
+            Packet::RRQ {
+                filename,
+                mode,
+                options,
+            } => {
                 let mut bytes = vec![0u8, 1];
                 bytes.extend(filename.as_bytes());
                 bytes.push(0);
                 bytes.extend(Into::<&str>::into(&mode).as_bytes());
                 bytes.push(0);
+                extend_with_options(&mut bytes, options);
                 bytes
             }
-            Packet::WRQ { filename, mode } => {
+            Packet::WRQ {
+                filename,
+                mode,
+                options,
+            } => {
                 let mut bytes = vec![0u8, 2];
                 bytes.extend(filename.as_bytes());
                 bytes.push(0);
                 bytes.extend(Into::<&str>::into(&mode).as_bytes());
                 bytes.push(0);
+                extend_with_options(&mut bytes, options);
+                bytes
+            }
+            Packet::OACK { options } => {
+                let mut bytes = vec![0u8, 6];
+                extend_with_options(&mut bytes, options);
                 bytes
             }
             Packet::DATA { block_number, data } => {
@@ -329,6 +557,7 @@ mod tests {
         assert_eq!(Ok(Opcode::DATA), 3.try_into());
         assert_eq!(Ok(Opcode::ACK), 4.try_into());
         assert_eq!(Ok(Opcode::ERROR), 5.try_into());
+        assert_eq!(Ok(Opcode::OACK), 6.try_into());
     }
 
     #[test]
@@ -340,6 +569,7 @@ mod tests {
             Packet::RRQ {
                 filename: "CDE".to_string(),
                 mode: Mode::Octet,
+                options: vec![],
             }
         );
     }
@@ -353,6 +583,39 @@ mod tests {
             Packet::WRQ {
                 filename: "CDE".to_string(),
                 mode: Mode::Octet,
+                options: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_rrq_parser_with_options() {
+        let mut packet = vec![0u8, 1, 67, 68, 69, 0, 0x6f, 0x63, 0x74, 0x65, 0x74, 0];
+        packet.extend(b"BLKSIZE\x001024\x00");
+        packet.extend(b"timeout\x005\x00");
+        let packet = Packet::from_bytes(&packet).unwrap();
+        assert_eq!(
+            packet,
+            Packet::RRQ {
+                filename: "CDE".to_string(),
+                mode: Mode::Octet,
+                options: vec![
+                    ("blksize".to_string(), "1024".to_string()),
+                    ("timeout".to_string(), "5".to_string()),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_oack_parser() {
+        let mut packet = vec![0u8, 6];
+        packet.extend(b"blksize\x001024\x00");
+        let packet = Packet::from_bytes(&packet).unwrap();
+        assert_eq!(
+            packet,
+            Packet::OACK {
+                options: vec![("blksize".to_string(), "1024".to_string())],
             }
         );
     }
@@ -392,7 +655,7 @@ mod tests {
 
     #[test]
     fn test_invalid_opcode() {
-        let packet = &[0u8, 6, 67, 68, 69, 0, 0x6f, 0x63, 0x74, 0x65, 0x74, 0];
+        let packet = &[0u8, 7, 67, 68, 69, 0, 0x6f, 0x63, 0x74, 0x65, 0x74, 0];
         assert!(matches!(
             Packet::from_bytes(packet),
             Err(PacketError::InvalidOpcode(..))
@@ -423,6 +686,7 @@ mod tests {
         let packet = Packet::RRQ {
             filename: "CDE".to_string(),
             mode: Mode::Octet,
+            options: vec![],
         };
         assert_eq!(
             packet.to_bytes(),
@@ -435,6 +699,7 @@ mod tests {
         let packet = Packet::WRQ {
             filename: "CDE".to_string(),
             mode: Mode::Octet,
+            options: vec![],
         };
         assert_eq!(
             packet.to_bytes(),
@@ -442,6 +707,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rrq_to_bytes_with_options() {
+        let packet = Packet::RRQ {
+            filename: "CDE".to_string(),
+            mode: Mode::Octet,
+            options: vec![("blksize".to_string(), "1024".to_string())],
+        };
+        let mut expected = vec![0u8, 1, 67, 68, 69, 0, 0x6f, 0x63, 0x74, 0x65, 0x74, 0];
+        expected.extend(b"blksize\x001024\x00");
+        assert_eq!(packet.to_bytes(), expected);
+    }
+
+    #[test]
+    fn test_oack_to_bytes() {
+        let packet = Packet::OACK {
+            options: vec![("blksize".to_string(), "1024".to_string())],
+        };
+        let mut expected = vec![0u8, 6];
+        expected.extend(b"blksize\x001024\x00");
+        assert_eq!(packet.to_bytes(), expected);
+    }
+
     #[test]
     fn test_data_to_bytes() {
         let packet = Packet::DATA {
@@ -465,4 +752,81 @@ mod tests {
         };
         assert_eq!(packet.to_bytes(), vec![0u8, 5, 0, 2, 67, 68, 69, 0]);
     }
+
+    #[test]
+    fn test_streaming_complete_packet() {
+        let packet = &[0u8, 4, 0, 42];
+        assert_eq!(
+            Packet::from_bytes_streaming(packet).unwrap(),
+            (&[][..], Some(Packet::ACK { block_number: 42 }))
+        );
+    }
+
+    #[test]
+    fn test_streaming_incomplete_opcode() {
+        let packet = &[0u8];
+        assert_eq!(
+            Packet::from_bytes_streaming(packet).unwrap(),
+            (&packet[..], None)
+        );
+    }
+
+    #[test]
+    fn test_streaming_incomplete_filename() {
+        let packet = &[0u8, 1, 67, 68, 69];
+        assert_eq!(
+            Packet::from_bytes_streaming(packet).unwrap(),
+            (&packet[..], None)
+        );
+    }
+
+    #[test]
+    fn test_streaming_incomplete_mode() {
+        let packet = &[0u8, 1, 67, 68, 69, 0, 0x6f, 0x63];
+        assert_eq!(
+            Packet::from_bytes_streaming(packet).unwrap(),
+            (&packet[..], None)
+        );
+    }
+
+    #[test]
+    fn test_streaming_incomplete_ack() {
+        let packet = &[0u8, 4, 0];
+        assert_eq!(
+            Packet::from_bytes_streaming(packet).unwrap(),
+            (&packet[..], None)
+        );
+    }
+
+    #[test]
+    fn test_streaming_trailing_bytes() {
+        let mut packet = vec![0u8, 5, 0, 2, 67, 68, 69, 0];
+        packet.extend([1, 2, 3]);
+        let (rest, parsed) = Packet::from_bytes_streaming(&packet).unwrap();
+        assert_eq!(rest, &[1, 2, 3]);
+        assert_eq!(
+            parsed,
+            Some(Packet::ERROR {
+                error_code: ErrorCode::AccessViolation,
+                error_msg: "CDE".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_streaming_ack_trailing_bytes() {
+        let packet = &[0u8, 4, 0, 1, 0, 4, 0, 2];
+        let (rest, parsed) = Packet::from_bytes_streaming(packet).unwrap();
+        assert_eq!(rest, &[0, 4, 0, 2]);
+        assert_eq!(parsed, Some(Packet::ACK { block_number: 1 }));
+    }
+
+    #[test]
+    fn test_streaming_malformed_is_not_incomplete() {
+        let packet = &[0u8, 99, 67, 68, 69, 0];
+        assert!(matches!(
+            Packet::from_bytes_streaming(packet),
+            Err(PacketError::InvalidOpcode(..))
+        ));
+    }
 }
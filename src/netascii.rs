@@ -0,0 +1,174 @@
+//! Netascii line-ending translation for `Mode::Netascii` payloads, as required by rfc1350:
+//! on the wire, lines are terminated with CR LF and a literal CR is escaped as CR NUL.
+
+use crate::{Mode, PacketError};
+
+impl Mode {
+    /// Encode raw bytes as rfc1350 netascii: a bare LF becomes CR LF and a bare CR becomes
+    /// CR NUL.
+    pub fn to_netascii(bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(bytes.len());
+
+        for &b in bytes {
+            match b {
+                b'\n' => out.extend_from_slice(b"\r\n"),
+                b'\r' => out.extend_from_slice(&[b'\r', 0]),
+                _ => out.push(b),
+            }
+        }
+
+        out
+    }
+
+    /// Decode a single, complete buffer of rfc1350 netascii back to raw bytes: CR LF becomes
+    /// LF, CR NUL becomes a lone CR. For a transfer split across DATA block boundaries, use
+    /// `NetasciiDecoder` instead, so a CR at the end of one block can be resolved against the
+    /// start of the next.
+    pub fn from_netascii(bytes: &[u8]) -> Result<Vec<u8>, PacketError> {
+        let mut decoder = NetasciiDecoder::new();
+        let decoded = decoder.decode(bytes)?;
+
+        if decoder.pending_cr {
+            return Err(PacketError::InvalidPacket(
+                "Error while decoding netascii. Buffer ends with an unterminated CR.".to_string(),
+            ));
+        }
+
+        Ok(decoded)
+    }
+}
+
+/// A resumable rfc1350 netascii decoder. A DATA block that ends on a lone CR can't yet tell
+/// whether it's the start of a CR LF or a CR NUL escape, so that pending CR is carried into
+/// the next call to `decode` instead of being resolved (or rejected) too early.
+#[derive(Debug, Default)]
+pub struct NetasciiDecoder {
+    pending_cr: bool,
+}
+
+impl NetasciiDecoder {
+    /// Create a decoder with no pending state, ready for the first DATA block of a transfer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode one block of netascii-encoded bytes. A CR left dangling at the end of `bytes`
+    /// is remembered and resolved (or rejected) on the next call.
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<Vec<u8>, PacketError> {
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+
+        if self.pending_cr {
+            match bytes.first() {
+                Some(b'\n') => {
+                    out.push(b'\n');
+                    i = 1;
+                    self.pending_cr = false;
+                }
+                Some(0) => {
+                    out.push(b'\r');
+                    i = 1;
+                    self.pending_cr = false;
+                }
+                Some(_) => {
+                    return Err(PacketError::InvalidPacket(
+                        "Error while decoding netascii. CR must be followed by LF or NUL."
+                            .to_string(),
+                    ));
+                }
+                None => return Ok(out),
+            }
+        }
+
+        while i < bytes.len() {
+            let b = bytes[i];
+
+            if b == b'\r' {
+                match bytes.get(i + 1) {
+                    Some(b'\n') => {
+                        out.push(b'\n');
+                        i += 2;
+                    }
+                    Some(0) => {
+                        out.push(b'\r');
+                        i += 2;
+                    }
+                    Some(_) => {
+                        return Err(PacketError::InvalidPacket(
+                            "Error while decoding netascii. CR must be followed by LF or NUL."
+                                .to_string(),
+                        ));
+                    }
+                    None => {
+                        self.pending_cr = true;
+                        i += 1;
+                    }
+                }
+            } else {
+                out.push(b);
+                i += 1;
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_netascii() {
+        assert_eq!(Mode::to_netascii(b"a\nb\rc"), b"a\r\nb\r\0c".to_vec());
+    }
+
+    #[test]
+    fn test_from_netascii() {
+        assert_eq!(Mode::from_netascii(b"a\r\nb\r\0c").unwrap(), b"a\nb\rc".to_vec());
+    }
+
+    #[test]
+    fn test_from_netascii_lone_cr_errors() {
+        assert!(matches!(
+            Mode::from_netascii(b"a\rb"),
+            Err(PacketError::InvalidPacket(..))
+        ));
+    }
+
+    #[test]
+    fn test_from_netascii_trailing_cr_errors() {
+        assert!(matches!(
+            Mode::from_netascii(b"abc\r"),
+            Err(PacketError::InvalidPacket(..))
+        ));
+    }
+
+    #[test]
+    fn test_netascii_decoder_resumes_across_blocks() {
+        let mut decoder = NetasciiDecoder::new();
+        let first = decoder.decode(b"hello\r").unwrap();
+        assert_eq!(first, b"hello".to_vec());
+
+        let second = decoder.decode(b"\nworld").unwrap();
+        assert_eq!(second, b"\nworld".to_vec());
+    }
+
+    #[test]
+    fn test_netascii_decoder_resumes_cr_nul_across_blocks() {
+        let mut decoder = NetasciiDecoder::new();
+        let first = decoder.decode(b"hello\r").unwrap();
+        assert_eq!(first, b"hello".to_vec());
+
+        let second = decoder.decode(&[0, b'!']).unwrap();
+        assert_eq!(second, vec![b'\r', b'!']);
+    }
+
+    #[test]
+    fn test_netascii_round_trip() {
+        let original = b"line one\nline two\rline three";
+        let encoded = Mode::to_netascii(original);
+        let decoded = Mode::from_netascii(&encoded).unwrap();
+        assert_eq!(decoded, original.to_vec());
+    }
+}
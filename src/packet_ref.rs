@@ -0,0 +1,306 @@
+//! A zero-copy, borrowed companion to `Packet` for hot receive loops that want to inspect and
+//! route packets without allocating per datagram.
+
+use std::convert::TryFrom;
+use std::str::from_utf8;
+
+use crate::parsing::take_till_null;
+use crate::parsing::take_u16;
+use crate::{ErrorCode, Mode, Opcode, Packet, PacketError};
+
+/// A borrowed view over a packet's bytes, mirroring `Packet` but copying no bytes out of the
+/// input while parsing: filenames, messages and option pairs stay as `&str` slices validated
+/// in place, the mode is parsed into a `Mode`, and DATA borrows its payload directly out of
+/// the input buffer. A successful parse performs no heap allocation, except that the option
+/// pairs of an `RRQ`/`WRQ`/`OACK` carrying options are collected into a `Vec`. Following the
+/// zerocopy-style approach used in the Fuchsia TFTP library, this lets a high-throughput
+/// server inspect and route packets before deciding whether an owned `Packet` is worth the
+/// allocation. Unlike `Packet`, option names here are kept exactly as they appeared on the
+/// wire rather than lowercased, since normalizing them would require an allocation.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PacketRef<'a> {
+    RRQ {
+        filename: &'a str,
+        mode: Mode,
+        options: Vec<(&'a str, &'a str)>,
+    },
+    WRQ {
+        filename: &'a str,
+        mode: Mode,
+        options: Vec<(&'a str, &'a str)>,
+    },
+    DATA {
+        block_number: u16,
+        data: &'a [u8],
+    },
+    ACK {
+        block_number: u16,
+    },
+    ERROR {
+        error_code: ErrorCode,
+        error_msg: &'a str,
+    },
+    OACK {
+        options: Vec<(&'a str, &'a str)>,
+    },
+}
+
+impl<'a> PacketRef<'a> {
+    /// Parse a borrowed packet view from a byte array. Same wire format as
+    /// `Packet::from_bytes`, but without copying any filename, mode, message or payload bytes.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, PacketError> {
+        Self::from_bytes_with_blksize(bytes, 512)
+    }
+
+    /// Like `from_bytes`, but accepts DATA payloads up to `max_block` bytes, matching a
+    /// negotiated `blksize` (RFC 2348).
+    pub fn from_bytes_with_blksize(
+        bytes: &'a [u8],
+        max_block: usize,
+    ) -> Result<Self, PacketError> {
+        let (bytes, opcode_bytes) = take_u16(bytes).map_err(|_| {
+            PacketError::InvalidOpcode("Error while parsing opcode. Opcode not a u15.".to_string())
+        })?;
+
+        let opcode = Opcode::try_from(opcode_bytes).map_err(|e| {
+            PacketError::InvalidOpcode(format!("Error while parsing opcode: {}", e))
+        })?;
+
+        match opcode {
+            Opcode::RRQ => {
+                let (filename, bytes) = parse_str_field(bytes, "filename")?;
+                let (mode, bytes) = parse_mode_ref(bytes)?;
+                let options = parse_options_ref(bytes)?;
+
+                Ok(PacketRef::RRQ {
+                    filename,
+                    mode,
+                    options,
+                })
+            }
+            Opcode::WRQ => {
+                let (filename, bytes) = parse_str_field(bytes, "filename")?;
+                let (mode, bytes) = parse_mode_ref(bytes)?;
+                let options = parse_options_ref(bytes)?;
+
+                Ok(PacketRef::WRQ {
+                    filename,
+                    mode,
+                    options,
+                })
+            }
+            Opcode::OACK => {
+                let options = parse_options_ref(bytes)?;
+
+                Ok(PacketRef::OACK { options })
+            }
+            Opcode::DATA => {
+                let (data, block_number) = take_u16(bytes).map_err(|_| {
+                    PacketError::InvalidPacket(
+                        "Error while parsing block number. Block number not a u16.".to_string(),
+                    )
+                })?;
+
+                if data.len() > max_block {
+                    Err(PacketError::InvalidPacketLength(max_block as u16))?
+                }
+
+                Ok(PacketRef::DATA { block_number, data })
+            }
+            Opcode::ACK => {
+                let (bytes, block_number) = take_u16(bytes).map_err(|_| {
+                    PacketError::InvalidPacket(
+                        "Error while parsing block number. Block number not a u16.".to_string(),
+                    )
+                })?;
+
+                if bytes.is_empty() {
+                    Ok(PacketRef::ACK { block_number })
+                } else {
+                    Err(PacketError::InvalidPacketLength(4))
+                }
+            }
+            Opcode::ERROR => {
+                let (bytes, error_code) = take_u16(bytes).map_err(|_| {
+                    PacketError::InvalidPacket(
+                        "Error while parsing error code. Error code not a u16.".to_string(),
+                    )
+                })?;
+                let error_code = ErrorCode::try_from(error_code).map_err(|_| {
+                    PacketError::InvalidPacket(
+                        "Error while parsing error code. Error code not a valid error code."
+                            .to_string(),
+                    )
+                })?;
+                let (error_msg, _bytes) = parse_str_field(bytes, "error message")?;
+
+                Ok(PacketRef::ERROR {
+                    error_code,
+                    error_msg,
+                })
+            }
+        }
+    }
+
+    /// Copy this borrowed view into an owned `Packet` that no longer depends on the input
+    /// buffer's lifetime.
+    pub fn to_owned(&self) -> Packet {
+        match self {
+            PacketRef::RRQ {
+                filename,
+                mode,
+                options,
+            } => Packet::RRQ {
+                filename: filename.to_string(),
+                mode: mode.clone(),
+                options: owned_options(options),
+            },
+            PacketRef::WRQ {
+                filename,
+                mode,
+                options,
+            } => Packet::WRQ {
+                filename: filename.to_string(),
+                mode: mode.clone(),
+                options: owned_options(options),
+            },
+            PacketRef::DATA { block_number, data } => Packet::DATA {
+                block_number: *block_number,
+                data: data.to_vec(),
+            },
+            PacketRef::ACK { block_number } => Packet::ACK {
+                block_number: *block_number,
+            },
+            PacketRef::ERROR {
+                error_code,
+                error_msg,
+            } => Packet::ERROR {
+                error_code: error_code.clone(),
+                error_msg: error_msg.to_string(),
+            },
+            PacketRef::OACK { options } => Packet::OACK {
+                options: owned_options(options),
+            },
+        }
+    }
+}
+
+fn owned_options(options: &[(&str, &str)]) -> Vec<(String, String)> {
+    options
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn parse_str_field<'a>(bytes: &'a [u8], field: &str) -> Result<(&'a str, &'a [u8]), PacketError> {
+    let (bytes, value) = take_till_null(bytes)
+        .map_err(|_| PacketError::InvalidPacket(format!("Error while parsing {}", field)))?;
+
+    if bytes.is_empty() {
+        return Err(PacketError::InvalidPacket(format!(
+            "Error while parsing {}. Missing null terminator.",
+            field
+        )));
+    }
+
+    let value = from_utf8(value).map_err(|_| {
+        PacketError::InvalidPacket(format!(
+            "Error while parsing {}. Not a valid UTF-8 string.",
+            field
+        ))
+    })?;
+
+    Ok((value, &bytes[1..]))
+}
+
+fn parse_mode_ref(bytes: &[u8]) -> Result<(Mode, &[u8]), PacketError> {
+    let (mode, bytes) = parse_str_field(bytes, "mode")?;
+
+    let mode: Mode = mode.try_into().map_err(|_| {
+        PacketError::InvalidPacket("Error while parsing mode. Not a valid mode string.".to_string())
+    })?;
+
+    Ok((mode, bytes))
+}
+
+fn parse_options_ref(bytes: &[u8]) -> Result<Vec<(&str, &str)>, PacketError> {
+    let mut options = Vec::new();
+    let mut bytes = bytes;
+
+    while !bytes.is_empty() {
+        let (name, rest) = parse_str_field(bytes, "option name")?;
+        let (value, rest) = parse_str_field(rest, "option value")?;
+
+        options.push((name, value));
+        bytes = rest;
+    }
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rrq_ref_parser() {
+        let packet = &[0u8, 1, 67, 68, 69, 0, 0x6f, 0x63, 0x74, 0x65, 0x74, 0];
+        let packet = PacketRef::from_bytes(packet).unwrap();
+        assert_eq!(
+            packet,
+            PacketRef::RRQ {
+                filename: "CDE",
+                mode: Mode::Octet,
+                options: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_rrq_ref_parser_with_options() {
+        let mut packet = vec![0u8, 1, 67, 68, 69, 0, 0x6f, 0x63, 0x74, 0x65, 0x74, 0];
+        packet.extend(b"blksize\x001024\x00");
+        let packet = PacketRef::from_bytes(&packet).unwrap();
+        assert_eq!(
+            packet,
+            PacketRef::RRQ {
+                filename: "CDE",
+                mode: Mode::Octet,
+                options: vec![("blksize", "1024")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_data_ref_parser_borrows_payload() {
+        let packet = &[0u8, 3, 0, 42, 67, 68, 69];
+        let parsed = PacketRef::from_bytes(packet).unwrap();
+        assert_eq!(
+            parsed,
+            PacketRef::DATA {
+                block_number: 42,
+                data: &[67, 68, 69],
+            }
+        );
+
+        if let PacketRef::DATA { data, .. } = parsed {
+            assert_eq!(data.as_ptr(), packet[4..].as_ptr());
+        } else {
+            panic!("expected DATA");
+        }
+    }
+
+    #[test]
+    fn test_packet_ref_to_owned() {
+        let bytes = &[0u8, 1, 67, 68, 69, 0, 0x6f, 0x63, 0x74, 0x65, 0x74, 0];
+        let owned = PacketRef::from_bytes(bytes).unwrap().to_owned();
+        assert_eq!(
+            owned,
+            Packet::RRQ {
+                filename: "CDE".to_string(),
+                mode: Mode::Octet,
+                options: vec![],
+            }
+        );
+    }
+}
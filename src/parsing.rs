@@ -5,6 +5,10 @@ use nom::{bytes::complete::*, IResult};
 
 use crate::{ErrorCode, Mode, PacketError};
 
+/// The option pairs trailing an `RRQ`/`WRQ`/`OACK`, as added by
+/// [RFC 2347](https://www.rfc-editor.org/rfc/rfc2347).
+pub type Options = Vec<(String, String)>;
+
 pub fn take_u16(input: &[u8]) -> IResult<&[u8], u16> {
     be_u16(input)
 }
@@ -13,6 +17,143 @@ pub fn take_till_null(input: &[u8]) -> IResult<&[u8], &[u8]> {
     take_till(|c| c == 0)(input)
 }
 
+/// Distinguishes a genuinely malformed buffer from one that is simply too short to contain a
+/// full field yet. Surfaces nom's `Incomplete` vs `Error` distinction to callers driving a
+/// streaming transport, instead of flattening both into a single "invalid packet" case.
+pub enum PartialError {
+    /// Not enough bytes are available yet; the caller should read more and retry.
+    Incomplete,
+    /// The bytes present are not a valid encoding of the expected field.
+    Malformed(PacketError),
+}
+
+impl From<PacketError> for PartialError {
+    fn from(error: PacketError) -> Self {
+        PartialError::Malformed(error)
+    }
+}
+
+fn streaming_result<'a, T>(
+    result: nom::IResult<&'a [u8], T, nom::error::Error<&'a [u8]>>,
+    malformed_msg: &str,
+) -> Result<(T, &'a [u8]), PartialError> {
+    match result {
+        Ok((rest, value)) => Ok((value, rest)),
+        Err(nom::Err::Incomplete(_)) => Err(PartialError::Incomplete),
+        Err(_) => Err(PartialError::Malformed(PacketError::InvalidPacket(
+            malformed_msg.to_string(),
+        ))),
+    }
+}
+
+pub fn take_u16_streaming(input: &[u8]) -> Result<(u16, &[u8]), PartialError> {
+    streaming_result(
+        nom::number::streaming::be_u16(input),
+        "Error while parsing a u16 field",
+    )
+}
+
+pub fn take_till_null_streaming(input: &[u8]) -> Result<(&[u8], &[u8]), PartialError> {
+    streaming_result(
+        nom::bytes::streaming::take_till(|c| c == 0)(input),
+        "Error while scanning for a null terminator",
+    )
+}
+
+pub fn parse_filename_streaming(bytes: &[u8]) -> Result<(String, &[u8]), PartialError> {
+    let (filename, bytes) = take_till_null_streaming(bytes)?;
+
+    let filename = from_utf8(filename)
+        .map_err(|_| {
+            PacketError::InvalidPacket(
+                "Error while parsing filename. Not a valid UTF-8 string.".to_string(),
+            )
+        })?
+        .to_string();
+
+    Ok((filename, &bytes[1..]))
+}
+
+pub fn parse_mode_streaming(bytes: &[u8]) -> Result<(Mode, &[u8]), PartialError> {
+    let (mode_bytes, bytes) = take_till_null_streaming(bytes)?;
+
+    let mode = from_utf8(mode_bytes).map_err(|_| {
+        PacketError::InvalidPacket(
+            "Error while parsing mode. Not a valid UTF-8 string.".to_string(),
+        )
+    })?;
+
+    let mode: Mode = mode.try_into().map_err(|_| {
+        PacketError::InvalidPacket("Error while parsing mode. Not a valid mode string.".to_string())
+    })?;
+
+    Ok((mode, &bytes[1..]))
+}
+
+pub fn parse_block_number_streaming(bytes: &[u8]) -> Result<(u16, &[u8]), PartialError> {
+    take_u16_streaming(bytes)
+}
+
+pub fn parse_error_code_streaming(bytes: &[u8]) -> Result<(ErrorCode, &[u8]), PartialError> {
+    let (error_code, bytes) = take_u16_streaming(bytes)?;
+
+    let error_code = ErrorCode::try_from(error_code).map_err(|_| {
+        PacketError::InvalidPacket(
+            "Error while parsing error code. Error code not a valid error code.".to_string(),
+        )
+    })?;
+
+    Ok((error_code, bytes))
+}
+
+pub fn parse_error_message_streaming(bytes: &[u8]) -> Result<(String, &[u8]), PartialError> {
+    let (error_msg, bytes) = take_till_null_streaming(bytes)?;
+
+    let error_msg = from_utf8(error_msg)
+        .map_err(|_| {
+            PacketError::InvalidPacket(
+                "Error while parsing error msg. Invalid UTF-8 string.".to_string(),
+            )
+        })?
+        .to_string();
+
+    Ok((error_msg, &bytes[1..]))
+}
+
+/// Like `parse_options`, but reports a trailing, not-yet-null-terminated option name/value as
+/// `PartialError::Incomplete` rather than as malformed. An empty remainder is treated as "no
+/// more options" rather than incomplete, since nothing on the wire marks the end of the list
+/// other than running out of bytes.
+pub fn parse_options_streaming(bytes: &[u8]) -> Result<(Options, &[u8]), PartialError> {
+    let mut options = Vec::new();
+    let mut bytes = bytes;
+
+    while !bytes.is_empty() {
+        let (name, rest) = take_till_null_streaming(bytes)?;
+        let name = from_utf8(name)
+            .map_err(|_| {
+                PacketError::InvalidPacket(
+                    "Error while parsing option name. Not a valid UTF-8 string.".to_string(),
+                )
+            })?
+            .to_lowercase();
+
+        let (value, rest) = take_till_null_streaming(&rest[1..])?;
+        let value = from_utf8(value)
+            .map_err(|_| {
+                PacketError::InvalidPacket(
+                    "Error while parsing option value. Not a valid UTF-8 string.".to_string(),
+                )
+            })?
+            .to_string();
+
+        options.push((name, value));
+        bytes = &rest[1..];
+    }
+
+    Ok((options, bytes))
+}
+
 pub fn parse_filename(bytes: &[u8]) -> Result<(String, &[u8]), PacketError> {
     let (bytes, filename) = take_till_null(bytes)
         .map_err(|_| PacketError::InvalidPacket("Error while parsing filename".to_string()))?;
@@ -32,6 +173,12 @@ pub fn parse_mode(bytes: &[u8]) -> Result<(Mode, &[u8]), PacketError> {
     let (bytes, mode_bytes) = take_till_null(bytes)
         .map_err(|_| PacketError::InvalidPacket("Error while parsing mode".to_string()))?;
 
+    if bytes.is_empty() {
+        return Err(PacketError::InvalidPacket(
+            "Error while parsing mode. Missing null terminator.".to_string(),
+        ));
+    }
+
     let mode = from_utf8(mode_bytes).map_err(|_| {
         PacketError::InvalidPacket(
             "Error while parsing mode. Not a valid UTF-8 string.".to_string(),
@@ -42,7 +189,7 @@ pub fn parse_mode(bytes: &[u8]) -> Result<(Mode, &[u8]), PacketError> {
         PacketError::InvalidPacket("Error while parsing mode. Not a valid mode string.".to_string())
     })?;
 
-    Ok((mode, bytes))
+    Ok((mode, &bytes[1..]))
 }
 
 pub fn parse_block_number(bytes: &[u8]) -> Result<(u16, &[u8]), PacketError> {
@@ -71,6 +218,53 @@ pub fn parse_error_code(bytes: &[u8]) -> Result<(ErrorCode, &[u8]), PacketError>
     Ok((error_code, bytes))
 }
 
+/// Parse a trailing sequence of `name\0value\0` option pairs, as added to `RRQ`/`WRQ`/`OACK`
+/// by [RFC 2347](https://www.rfc-editor.org/rfc/rfc2347). Option names are case-insensitive on
+/// the wire and are lowercased here; values are kept as generic strings so unknown options
+/// still round-trip losslessly.
+pub fn parse_options(bytes: &[u8]) -> Result<(Options, &[u8]), PacketError> {
+    let mut options = Vec::new();
+    let mut bytes = bytes;
+
+    while !bytes.is_empty() {
+        let (rest, name) = take_till_null(bytes)
+            .map_err(|_| PacketError::InvalidPacket("Error while parsing option name".to_string()))?;
+        if rest.is_empty() {
+            return Err(PacketError::InvalidPacket(
+                "Error while parsing option name. Missing null terminator.".to_string(),
+            ));
+        }
+        let name = from_utf8(name)
+            .map_err(|_| {
+                PacketError::InvalidPacket(
+                    "Error while parsing option name. Not a valid UTF-8 string.".to_string(),
+                )
+            })?
+            .to_lowercase();
+
+        let (rest, value) = take_till_null(&rest[1..]).map_err(|_| {
+            PacketError::InvalidPacket("Error while parsing option value".to_string())
+        })?;
+        if rest.is_empty() {
+            return Err(PacketError::InvalidPacket(
+                "Error while parsing option value. Missing null terminator.".to_string(),
+            ));
+        }
+        let value = from_utf8(value)
+            .map_err(|_| {
+                PacketError::InvalidPacket(
+                    "Error while parsing option value. Not a valid UTF-8 string.".to_string(),
+                )
+            })?
+            .to_string();
+
+        options.push((name, value));
+        bytes = &rest[1..];
+    }
+
+    Ok((options, bytes))
+}
+
 pub fn parse_error_message(bytes: &[u8]) -> Result<(String, &[u8]), PacketError> {
     let (bytes, error_msg) = take_till_null(bytes)
         .map_err(|_| PacketError::InvalidPacket("Error while parsing error message".to_string()))?;